@@ -0,0 +1,38 @@
+use std::sync::RwLock;
+
+/// The identity of the user currently signed in to the app. Desktop builds
+/// run a single session at a time, so this is tracked as plain shared state
+/// rather than per-request tokens.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub username: String,
+    pub is_admin: bool,
+}
+
+impl Session {
+    pub fn can_access(&self, owner: &str) -> bool {
+        self.is_admin || self.username == owner
+    }
+}
+
+/// Stop-gap admin activation until there's a real login flow with roles:
+/// a username is an admin if it appears in the comma-separated `ADMIN_USERS`
+/// env var.
+pub fn is_admin_user(username: &str) -> bool {
+    std::env::var("ADMIN_USERS")
+        .map(|admins| admins.split(',').map(str::trim).any(|u| u == username))
+        .unwrap_or(false)
+}
+
+/// Tauri-managed holder for the current [`Session`].
+pub struct CurrentSession(pub RwLock<Session>);
+
+impl CurrentSession {
+    pub fn new(session: Session) -> Self {
+        Self(RwLock::new(session))
+    }
+
+    pub fn get(&self) -> Session {
+        self.0.read().expect("session lock poisoned").clone()
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+/// A dotted version vector: a map from replica node id to the highest
+/// write counter from that node this replica has observed. Used as the
+/// causal context a client must echo back when writing, so concurrent
+/// edits can be told apart from ones that legitimately supersede each
+/// other.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VersionVector(pub HashMap<String, u64>);
+
+/// A single write, identified by the replica that made it and that
+/// replica's counter at the time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dot<'a> {
+    pub node: &'a str,
+    pub counter: u64,
+}
+
+/// This replica's identity in the dotted version vector scheme. Stable for
+/// the lifetime of the installation so dots it writes stay comparable.
+pub struct NodeId(pub String);
+
+/// Loads this installation's [`NodeId`], generating and persisting one on
+/// first run. `NODE_ID` overrides the persisted value when set, for the rare
+/// case (tests, simulating multiple replicas) where a caller needs to pin
+/// the id explicitly instead of letting it stick across restarts.
+pub async fn load_or_create_node_id(pool: &SqlitePool) -> anyhow::Result<NodeId> {
+    if let Ok(node_id) = std::env::var("NODE_ID") {
+        return Ok(NodeId(node_id));
+    }
+
+    sqlx::query("INSERT INTO node_identity (id, node_id) VALUES (1, ?) ON CONFLICT(id) DO NOTHING")
+        .bind(Uuid::new_v4().to_string())
+        .execute(pool)
+        .await?;
+
+    let node_id: String = sqlx::query_scalar("SELECT node_id FROM node_identity WHERE id = 1")
+        .fetch_one(pool)
+        .await?;
+
+    Ok(NodeId(node_id))
+}
+
+impl VersionVector {
+    pub fn counter(&self, node: &str) -> u64 {
+        self.0.get(node).copied().unwrap_or(0)
+    }
+
+    /// Whether this vector's causal knowledge already includes `dot`, i.e.
+    /// a value written with `dot` would bring the reader nothing new.
+    pub fn dominates(&self, dot: Dot<'_>) -> bool {
+        self.counter(dot.node) >= dot.counter
+    }
+
+    /// Pointwise-max merge of two vectors, keeping the highest counter
+    /// known for each node. Dots covered by both sides are naturally
+    /// folded away, which is what keeps the vector compact over time.
+    pub fn merge(&self, other: &VersionVector) -> VersionVector {
+        let mut merged = self.0.clone();
+        for (node, counter) in &other.0 {
+            let entry = merged.entry(node.clone()).or_insert(0);
+            if *counter > *entry {
+                *entry = *counter;
+            }
+        }
+        VersionVector(merged)
+    }
+
+    pub fn advanced(&self, node: &str) -> (VersionVector, u64) {
+        let counter = self.counter(node) + 1;
+        let mut advanced = self.0.clone();
+        advanced.insert(node.to_string(), counter);
+        (VersionVector(advanced), counter)
+    }
+
+    /// Returns a copy of this vector with `node`'s counter set to `counter`,
+    /// for callers that already obtained the counter atomically elsewhere
+    /// (e.g. a `model_node_counters` bump) and just need it folded in.
+    pub fn with_counter(&self, node: &str, counter: u64) -> VersionVector {
+        let mut updated = self.0.clone();
+        updated.insert(node.to_string(), counter);
+        VersionVector(updated)
+    }
+
+    pub fn to_token(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_token(token: &str) -> Self {
+        serde_json::from_str(token).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vv(pairs: &[(&str, u64)]) -> VersionVector {
+        VersionVector(pairs.iter().map(|(n, c)| (n.to_string(), *c)).collect())
+    }
+
+    #[test]
+    fn dominates_is_true_for_a_known_or_superseded_dot() {
+        let known = vv(&[("a", 3)]);
+        assert!(known.dominates(Dot { node: "a", counter: 1 }));
+        assert!(known.dominates(Dot { node: "a", counter: 3 }));
+    }
+
+    #[test]
+    fn dominates_is_false_for_a_concurrent_or_unseen_dot() {
+        let known = vv(&[("a", 3)]);
+        assert!(!known.dominates(Dot { node: "a", counter: 4 }));
+        assert!(!known.dominates(Dot { node: "b", counter: 1 }));
+    }
+
+    #[test]
+    fn merge_keeps_the_max_per_node() {
+        let a = vv(&[("a", 3), ("b", 1)]);
+        let b = vv(&[("a", 2), ("b", 5), ("c", 1)]);
+        assert_eq!(a.merge(&b), vv(&[("a", 3), ("b", 5), ("c", 1)]));
+    }
+
+    #[test]
+    fn advanced_increments_only_the_given_node() {
+        let known = vv(&[("a", 3), ("b", 1)]);
+        let (advanced, counter) = known.advanced("a");
+        assert_eq!(counter, 4);
+        assert_eq!(advanced, vv(&[("a", 4), ("b", 1)]));
+    }
+
+    #[test]
+    fn advanced_starts_an_unseen_node_at_one() {
+        let known = VersionVector::default();
+        let (advanced, counter) = known.advanced("a");
+        assert_eq!(counter, 1);
+        assert_eq!(advanced, vv(&[("a", 1)]));
+    }
+
+    #[test]
+    fn token_round_trips() {
+        let known = vv(&[("a", 3), ("b", 1)]);
+        assert_eq!(VersionVector::from_token(&known.to_token()), known);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::auth::CurrentSession;
+use crate::database::models::ModelTypeCount;
+use crate::storage::ModelFileStore;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
+use tracing::{info, instrument};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelsStats {
+    pub counts_by_type: Vec<ModelTypeCount>,
+    pub total_blob_bytes: i64,
+    pub orphaned_blob_count: i64,
+}
+
+/// `report` only lists what `repair_models` would change; `clean` also
+/// applies the fix.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairMode {
+    Report,
+    Clean,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RepairReport {
+    /// Models whose `content_hash` points at a blob that no longer exists
+    /// on disk.
+    pub models_missing_blob: Vec<String>,
+    /// Blobs tracked in the `blobs` table that no model references anymore.
+    pub orphaned_blob_hashes: Vec<String>,
+    pub applied: bool,
+}
+
+fn require_admin(session: &State<'_, CurrentSession>) -> Result<(), String> {
+    if session.get().is_admin {
+        Ok(())
+    } else {
+        Err("admin role required".to_string())
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(pool, session))]
+pub async fn models_stats(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, CurrentSession>,
+) -> Result<ModelsStats, String> {
+    require_admin(&session)?;
+
+    let counts_by_type: Vec<ModelTypeCount> = sqlx::query_as(
+        "SELECT model_type, count FROM model_type_counts ORDER BY model_type",
+    )
+    .fetch_all(pool.inner())
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let total_blob_bytes: i64 =
+        sqlx::query_scalar("SELECT COALESCE(SUM(byte_length), 0) FROM blobs")
+            .fetch_one(pool.inner())
+            .await
+            .map_err(|e| e.to_string())?;
+
+    let orphaned_blob_count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM blobs
+         WHERE hash NOT IN (SELECT content_hash FROM models WHERE content_hash IS NOT NULL)",
+    )
+    .fetch_one(pool.inner())
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(ModelsStats {
+        counts_by_type,
+        total_blob_bytes,
+        orphaned_blob_count,
+    })
+}
+
+/// Scans for drift between the `models`/`blobs` rows and the on-disk blob
+/// store, and either reports it (`RepairMode::Report`) or fixes it
+/// (`RepairMode::Clean`): models pointing at a missing blob have their
+/// `content_hash`/`byte_length` cleared, and orphaned blob rows (and their
+/// files) are removed.
+#[tauri::command]
+#[instrument(skip(pool, store, session))]
+pub async fn repair_models(
+    pool: State<'_, SqlitePool>,
+    store: State<'_, ModelFileStore>,
+    session: State<'_, CurrentSession>,
+    mode: RepairMode,
+) -> Result<RepairReport, String> {
+    require_admin(&session)?;
+    info!("Running models repair in {:?} mode", mode);
+
+    let models_with_hash: Vec<(String, String)> =
+        sqlx::query_as("SELECT id, content_hash FROM models WHERE content_hash IS NOT NULL")
+            .fetch_all(pool.inner())
+            .await
+            .map_err(|e| e.to_string())?;
+
+    let mut models_missing_blob = Vec::new();
+    for (model_id, hash) in &models_with_hash {
+        if tokio::fs::metadata(store.blob_path(hash)).await.is_err() {
+            models_missing_blob.push(model_id.clone());
+        }
+    }
+
+    let applied = matches!(mode, RepairMode::Clean);
+    if applied && !models_missing_blob.is_empty() {
+        let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+        for model_id in &models_missing_blob {
+            sqlx::query(
+                "UPDATE models SET content_hash = NULL, byte_length = NULL WHERE id = ?",
+            )
+            .bind(model_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        tx.commit().await.map_err(|e| e.to_string())?;
+    }
+
+    // Computed after clearing the rows above so a model that just lost its
+    // blob reference in this same call shows up as orphaned immediately,
+    // instead of needing a second `repair_models(Clean)` to catch it.
+    let orphaned_blob_hashes: Vec<String> = sqlx::query_scalar(
+        "SELECT hash FROM blobs
+         WHERE hash NOT IN (SELECT content_hash FROM models WHERE content_hash IS NOT NULL)",
+    )
+    .fetch_all(pool.inner())
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if applied && !orphaned_blob_hashes.is_empty() {
+        let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+        for hash in &orphaned_blob_hashes {
+            sqlx::query("DELETE FROM blobs WHERE hash = ?")
+                .bind(hash)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        tx.commit().await.map_err(|e| e.to_string())?;
+
+        for hash in &orphaned_blob_hashes {
+            store.remove_blob(hash).await.map_err(|e| e.to_string())?;
+        }
+    }
+
+    info!(
+        "Repair found {} models missing a blob and {} orphaned blobs (applied={})",
+        models_missing_blob.len(),
+        orphaned_blob_hashes.len(),
+        applied
+    );
+
+    Ok(RepairReport {
+        models_missing_blob,
+        orphaned_blob_hashes,
+        applied,
+    })
+}
@@ -1,53 +1,143 @@
-"keyword">use crate::database::models::Model;
-"keyword">use sqlx::SqlitePool;
-"keyword">use tauri::State;
-"keyword">use anyhow::Result;
-"keyword">use tracing::{info, instrument};
-"keyword">use uuid::Uuid;
-"keyword">use chrono::Utc;
+use crate::auth::CurrentSession;
+use crate::causal::{Dot, NodeId, VersionVector};
+use crate::database::models::{Model, ModelPage, ModelTypeCount, ModelValue, UpdateModelResult};
+use crate::storage::ModelFileStore;
+use chrono::Utc;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use tauri::State;
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+
+/// Parses a `start_after` cursor of the form `"<created_at_rfc3339>_<id>"`
+/// as produced by the previous page's `next_cursor`.
+fn parse_cursor(cursor: &str) -> Result<(chrono::DateTime<Utc>, &str), String> {
+    let (created_at, id) = cursor
+        .split_once('_')
+        .ok_or_else(|| "invalid cursor".to_string())?;
+    let created_at = chrono::DateTime::parse_from_rfc3339(created_at)
+        .map_err(|e| e.to_string())?
+        .with_timezone(&Utc);
+    Ok((created_at, id))
+}
 
 #[tauri::command]
 #[instrument(skip(pool))]
-"keyword">pub "keyword">async "keyword">fn get_models(pool: State<'_, SqlitePool>) -> Result<Vec<Model>, String> {
-    info!("Fetching all models");
-    
-    "keyword">let mock_models = vec![
-        Model {
-            id: Uuid::new_v4().to_string(),
-            name: "DeepSeek-R1".to_string(),
-            description: Some("Text generation model".to_string()),
-            model_type: "text-generation".to_string(),
-            size: "XL 22B".to_string(),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        },
-        Model {
-            id: Uuid::new_v4().to_string(),
-            name: "DeepSeek-R1".to_string(),
-            description: Some("Compact model".to_string()),
-            model_type: "text-generation".to_string(),
-            size: "3.9 ML".to_string(),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        },
-    ];
-    
-    info!("Returning {} models", mock_models.len());
-    Ok(mock_models)
+pub async fn get_models(
+    pool: State<'_, SqlitePool>,
+    limit: Option<i64>,
+    start_after: Option<String>,
+    model_type: Option<String>,
+    name_contains: Option<String>,
+) -> Result<ModelPage, String> {
+    info!("Fetching models page (limit={:?})", limit);
+
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, 500);
+
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT id, name, description, model_type, size, created_at, updated_at,
+                content_hash, byte_length, owner,
+                (SELECT COUNT(*) > 1 FROM model_values WHERE model_values.model_id = models.id) AS conflicted,
+                COALESCE((SELECT vector_json FROM model_context WHERE model_context.model_id = models.id), '{}') AS causal_context
+         FROM models WHERE 1 = 1",
+    );
+
+    if let Some(model_type) = &model_type {
+        query.push(" AND model_type = ").push_bind(model_type);
+    }
+    if let Some(name_contains) = &name_contains {
+        query
+            .push(" AND name LIKE ")
+            .push_bind(format!("%{name_contains}%"));
+    }
+    if let Some(start_after) = &start_after {
+        let (created_at, id) = parse_cursor(start_after)?;
+        query
+            .push(" AND (created_at, id) < (")
+            .push_bind(created_at)
+            .push(", ")
+            .push_bind(id)
+            .push(")");
+    }
+
+    query
+        .push(" ORDER BY created_at DESC, id DESC LIMIT ")
+        .push_bind(limit + 1);
+
+    let mut models = query
+        .build_query_as::<Model>()
+        .fetch_all(pool.inner())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let next_cursor = if models.len() as i64 > limit {
+        models.truncate(limit as usize);
+        models
+            .last()
+            .map(|m| format!("{}_{}", m.created_at.to_rfc3339(), m.id))
+    } else {
+        None
+    };
+
+    info!("Returning {} models", models.len());
+    Ok(ModelPage { models, next_cursor })
+}
+
+/// Returns the models owned by `username`. Admins may pass any username and
+/// see that user's models; non-admins may only query their own.
+#[tauri::command]
+#[instrument(skip(pool, session))]
+pub async fn get_models_by_user(
+    pool: State<'_, SqlitePool>,
+    session: State<'_, CurrentSession>,
+    username: String,
+) -> Result<Vec<Model>, String> {
+    let caller = session.get();
+    if !caller.is_admin && caller.username != username {
+        return Err("not authorized to view another user's models".to_string());
+    }
+
+    sqlx::query_as::<_, Model>(
+        "SELECT id, name, description, model_type, size, created_at, updated_at,
+                content_hash, byte_length, owner,
+                (SELECT COUNT(*) > 1 FROM model_values WHERE model_values.model_id = models.id) AS conflicted,
+                COALESCE((SELECT vector_json FROM model_context WHERE model_context.model_id = models.id), '{}') AS causal_context
+         FROM models WHERE owner = ?
+         ORDER BY created_at DESC",
+    )
+    .bind(&username)
+    .fetch_all(pool.inner())
+    .await
+    .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 #[instrument(skip(pool))]
-"keyword">pub "keyword">async "keyword">fn create_model(
+pub async fn get_model_counts(pool: State<'_, SqlitePool>) -> Result<Vec<ModelTypeCount>, String> {
+    sqlx::query_as::<_, ModelTypeCount>(
+        "SELECT model_type, count FROM model_type_counts ORDER BY model_type",
+    )
+    .fetch_all(pool.inner())
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[instrument(skip(pool, session))]
+pub async fn create_model(
     pool: State<'_, SqlitePool>,
+    session: State<'_, CurrentSession>,
     name: String,
     description: Option<String>,
     model_type: String,
     size: String,
 ) -> Result<Model, String> {
     info!("Creating new model: {}", name);
-    
-    "keyword">let model = Model {
+
+    let owner = session.get().username;
+
+    let model = Model {
         id: Uuid::new_v4().to_string(),
         name,
         description,
@@ -55,22 +145,472 @@
         size,
         created_at: Utc::now(),
         updated_at: Utc::now(),
+        content_hash: None,
+        byte_length: None,
+        owner,
+        conflicted: false,
+        causal_context: VersionVector::default().to_token(),
     };
-    
-    // TODO: Реальная запись в БД
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::query(
+        "INSERT INTO models (id, name, description, model_type, size, created_at, updated_at, owner)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&model.id)
+    .bind(&model.name)
+    .bind(&model.description)
+    .bind(&model.model_type)
+    .bind(&model.size)
+    .bind(model.created_at)
+    .bind(model.updated_at)
+    .bind(&model.owner)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    sqlx::query(
+        "INSERT INTO model_type_counts (model_type, count) VALUES (?, 1)
+         ON CONFLICT(model_type) DO UPDATE SET count = count + 1",
+    )
+    .bind(&model.model_type)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
     info!("Model created successfully");
     Ok(model)
 }
 
+/// Streams the weight file at `path` into the content-addressed store and
+/// attaches its hash to `model_id`. If the model already had a different
+/// blob attached, that blob's reference count is dropped and the blob is
+/// deleted once nothing references it anymore.
+#[tauri::command]
+#[instrument(skip(pool, store, session))]
+pub async fn import_model_file(
+    pool: State<'_, SqlitePool>,
+    store: State<'_, ModelFileStore>,
+    session: State<'_, CurrentSession>,
+    model_id: String,
+    path: String,
+) -> Result<Model, String> {
+    info!("Importing weight file for model {}: {}", model_id, path);
+
+    let owner: String = sqlx::query_scalar("SELECT owner FROM models WHERE id = ?")
+        .bind(&model_id)
+        .fetch_optional(pool.inner())
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "model not found".to_string())?;
+
+    if !session.get().can_access(&owner) {
+        return Err("not authorized to import a weight file for this model".to_string());
+    }
+
+    let (hash, byte_length) = store
+        .import(std::path::Path::new(&path))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let previous_hash: Option<String> =
+        sqlx::query_scalar("SELECT content_hash FROM models WHERE id = ?")
+            .bind(&model_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?
+            .flatten();
+
+    if previous_hash.as_deref() != Some(hash.as_str()) {
+        sqlx::query(
+            "INSERT INTO blobs (hash, byte_length, ref_count) VALUES (?, ?, 1)
+             ON CONFLICT(hash) DO UPDATE SET ref_count = ref_count + 1",
+        )
+        .bind(&hash)
+        .bind(byte_length as i64)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        if let Some(previous_hash) = previous_hash {
+            release_blob(&mut tx, &store, &previous_hash)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    sqlx::query(
+        "UPDATE models SET content_hash = ?, byte_length = ?, updated_at = ? WHERE id = ?",
+    )
+    .bind(&hash)
+    .bind(byte_length as i64)
+    .bind(Utc::now())
+    .bind(&model_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let model = sqlx::query_as::<_, Model>(
+        "SELECT id, name, description, model_type, size, created_at, updated_at,
+                content_hash, byte_length, owner,
+                (SELECT COUNT(*) > 1 FROM model_values WHERE model_values.model_id = models.id) AS conflicted,
+                COALESCE((SELECT vector_json FROM model_context WHERE model_context.model_id = models.id), '{}') AS causal_context
+         FROM models WHERE id = ?",
+    )
+    .bind(&model_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    info!("Weight file imported successfully");
+    Ok(model)
+}
+
+/// Applies a metadata edit using a dotted version vector for conflict
+/// detection. `causal_context` is the token the caller read alongside the
+/// value it's editing (empty/absent for a blind write). Any stored sibling
+/// value whose dot the incoming context already covers is superseded and
+/// discarded; anything still concurrent with it survives alongside the new
+/// write, leaving the model "conflicted" until a future write resolves it.
+#[tauri::command]
+#[instrument(skip(pool, node_id, session))]
+pub async fn update_model(
+    pool: State<'_, SqlitePool>,
+    node_id: State<'_, NodeId>,
+    session: State<'_, CurrentSession>,
+    model_id: String,
+    causal_context: Option<String>,
+    name: String,
+    description: Option<String>,
+    model_type: String,
+    size: String,
+) -> Result<UpdateModelResult, String> {
+    info!("Updating model {} (node={})", model_id, node_id.0);
+
+    let incoming = causal_context
+        .as_deref()
+        .map(VersionVector::from_token)
+        .unwrap_or_default();
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let (owner, previous_model_type): (String, String) =
+        sqlx::query_as("SELECT owner, model_type FROM models WHERE id = ?")
+            .bind(&model_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "model not found".to_string())?;
+
+    if !session.get().can_access(&owner) {
+        return Err("not authorized to update this model".to_string());
+    }
+
+    // Bumped first, before any other write in this transaction: SQLite only
+    // lets one writer hold the lock this UPSERT needs at a time, so this is
+    // where we serialize against a concurrent update_model for the same
+    // (model_id, node). Reading current_vector/siblings only after this
+    // point means they reflect every write that has already committed,
+    // instead of a stale snapshot read before we had the lock.
+    let own_counter: i64 = sqlx::query_scalar(
+        "INSERT INTO model_node_counters (model_id, node_id, counter) VALUES (?, ?, 1)
+         ON CONFLICT(model_id, node_id) DO UPDATE SET counter = counter + 1
+         RETURNING counter",
+    )
+    .bind(&model_id)
+    .bind(&node_id.0)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    let own_counter = own_counter as u64;
+
+    let current_vector = sqlx::query_scalar::<_, String>(
+        "SELECT vector_json FROM model_context WHERE model_id = ?",
+    )
+    .bind(&model_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .map(|json| VersionVector::from_token(&json))
+    .unwrap_or_default();
+
+    let siblings: Vec<(i64, String, i64)> =
+        sqlx::query_as("SELECT id, dot_node, dot_counter FROM model_values WHERE model_id = ?")
+            .bind(&model_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    for (row_id, dot_node, dot_counter) in &siblings {
+        let dot = Dot {
+            node: dot_node,
+            counter: *dot_counter as u64,
+        };
+        if incoming.dominates(dot) {
+            sqlx::query("DELETE FROM model_values WHERE id = ?")
+                .bind(row_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    let advanced = current_vector.with_counter(&node_id.0, own_counter);
+    let merged = advanced.merge(&incoming);
+    let now = Utc::now();
+
+    sqlx::query(
+        "INSERT INTO model_values
+            (model_id, dot_node, dot_counter, name, description, model_type, size, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&model_id)
+    .bind(&node_id.0)
+    .bind(own_counter as i64)
+    .bind(&name)
+    .bind(&description)
+    .bind(&model_type)
+    .bind(&size)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    sqlx::query(
+        "INSERT INTO model_context (model_id, vector_json) VALUES (?, ?)
+         ON CONFLICT(model_id) DO UPDATE SET vector_json = excluded.vector_json",
+    )
+    .bind(&model_id)
+    .bind(merged.to_token())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let values: Vec<ModelValue> = sqlx::query_as(
+        "SELECT name, description, model_type, size, updated_at
+         FROM model_values WHERE model_id = ?
+         ORDER BY updated_at DESC",
+    )
+    .bind(&model_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let conflicted = values.len() > 1;
+
+    // The plain `models` row keeps showing a representative value so
+    // get_models/get_models_by_user stay useful without forcing every
+    // caller through the sibling list; `conflicted` is what tells them
+    // there's more than one to look at.
+    if let Some(latest) = values.first() {
+        sqlx::query(
+            "UPDATE models SET name = ?, description = ?, model_type = ?, size = ?, updated_at = ?
+             WHERE id = ?",
+        )
+        .bind(&latest.name)
+        .bind(&latest.description)
+        .bind(&latest.model_type)
+        .bind(&latest.size)
+        .bind(latest.updated_at)
+        .bind(&model_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        // model_type_counts mirrors the representative model_type shown on
+        // the `models` row, so it needs the same create_model/delete_model
+        // style bookkeeping whenever that representative type changes.
+        if latest.model_type != previous_model_type {
+            sqlx::query("UPDATE model_type_counts SET count = count - 1 WHERE model_type = ?")
+                .bind(&previous_model_type)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            sqlx::query(
+                "INSERT INTO model_type_counts (model_type, count) VALUES (?, 1)
+                 ON CONFLICT(model_type) DO UPDATE SET count = count + 1",
+            )
+            .bind(&latest.model_type)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    info!("Model updated; conflicted={}", conflicted);
+    Ok(UpdateModelResult {
+        causal_context: merged.to_token(),
+        values,
+        conflicted,
+    })
+}
+
+/// Returns every live sibling value for a model, together with the causal
+/// context token for them, so a caller can `update_model` without a prior
+/// `get_models` round-trip and without losing a concurrent edit.
 #[tauri::command]
 #[instrument(skip(pool))]
-"keyword">pub "keyword">async "keyword">fn delete_model(
+pub async fn get_model_values(
     pool: State<'_, SqlitePool>,
     model_id: String,
-) -> ResultString> {
+) -> Result<UpdateModelResult, String> {
+    let values: Vec<ModelValue> = sqlx::query_as(
+        "SELECT name, description, model_type, size, updated_at
+         FROM model_values WHERE model_id = ?
+         ORDER BY updated_at DESC",
+    )
+    .bind(&model_id)
+    .fetch_all(pool.inner())
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let causal_context = sqlx::query_scalar::<_, String>(
+        "SELECT vector_json FROM model_context WHERE model_id = ?",
+    )
+    .bind(&model_id)
+    .fetch_optional(pool.inner())
+    .await
+    .map_err(|e| e.to_string())?
+    .unwrap_or_else(|| VersionVector::default().to_token());
+
+    let conflicted = values.len() > 1;
+
+    Ok(UpdateModelResult {
+        causal_context,
+        values,
+        conflicted,
+    })
+}
+
+#[tauri::command]
+#[instrument(skip(pool, store, session))]
+pub async fn delete_model(
+    pool: State<'_, SqlitePool>,
+    store: State<'_, ModelFileStore>,
+    session: State<'_, CurrentSession>,
+    model_id: String,
+) -> Result<bool, String> {
     info!("Deleting model: {}", model_id);
-    
-    // TODO: Реальное удаление из БД
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let row: Option<(Option<String>, String, String)> =
+        sqlx::query_as("SELECT content_hash, model_type, owner FROM models WHERE id = ?")
+            .bind(&model_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    if let Some((_, _, owner)) = &row {
+        if !session.get().can_access(owner) {
+            return Err("not authorized to delete this model".to_string());
+        }
+    }
+
+    // model_values/model_context/model_node_counters declare REFERENCES
+    // models(id), and sqlx turns SQLite's foreign-key enforcement on by
+    // default, so these rows have to go before the model row itself.
+    sqlx::query("DELETE FROM model_values WHERE model_id = ?")
+        .bind(&model_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    sqlx::query("DELETE FROM model_context WHERE model_id = ?")
+        .bind(&model_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    sqlx::query("DELETE FROM model_node_counters WHERE model_id = ?")
+        .bind(&model_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let result = sqlx::query("DELETE FROM models WHERE id = ?")
+        .bind(&model_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some((content_hash, model_type, _)) = row {
+        sqlx::query("UPDATE model_type_counts SET count = count - 1 WHERE model_type = ?")
+            .bind(&model_type)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if let Some(hash) = content_hash {
+            release_blob(&mut tx, &store, &hash)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
     info!("Model deleted successfully");
-    Ok(true)
-}
\ No newline at end of file
+    Ok(result.rows_affected() > 0)
+}
+
+/// Drops one reference from `hash`'s blob, deleting the row and the
+/// on-disk blob once the count reaches zero.
+async fn release_blob(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    store: &ModelFileStore,
+    hash: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE blobs SET ref_count = ref_count - 1 WHERE hash = ?")
+        .bind(hash)
+        .execute(&mut **tx)
+        .await?;
+
+    let ref_count: Option<i64> = sqlx::query_scalar("SELECT ref_count FROM blobs WHERE hash = ?")
+        .bind(hash)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+    if matches!(ref_count, Some(n) if n <= 0) {
+        sqlx::query("DELETE FROM blobs WHERE hash = ?")
+            .bind(hash)
+            .execute(&mut **tx)
+            .await?;
+        store.remove_blob(hash).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips_through_the_format_get_models_produces() {
+        let created_at = Utc::now();
+        let id = Uuid::new_v4().to_string();
+        let cursor = format!("{}_{}", created_at.to_rfc3339(), id);
+
+        let (parsed_created_at, parsed_id) = parse_cursor(&cursor).expect("valid cursor");
+
+        assert_eq!(parsed_created_at, created_at);
+        assert_eq!(parsed_id, id);
+    }
+
+    #[test]
+    fn cursor_rejects_malformed_input() {
+        assert!(parse_cursor("not-a-cursor").is_err());
+        assert!(parse_cursor("not-rfc3339_some-id").is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use tracing::{info, instrument};
+
+/// Directory of numbered migrations, each a folder containing an `up.sql`.
+/// Folder names are expected to sort lexicographically in the order they
+/// should run, e.g. `0001_init`, `0002_model_file_store`.
+const MIGRATIONS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/migrations");
+
+#[instrument(skip(pool))]
+pub async fn run_pending_migrations(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version TEXT PRIMARY KEY,
+            applied_at TEXT NOT NULL
+        )",
+    )
+    .execute(pool)
+    .await
+    .context("failed to create schema_migrations table")?;
+
+    let mut versions: Vec<String> = std::fs::read_dir(MIGRATIONS_DIR)
+        .with_context(|| format!("failed to read migrations dir {MIGRATIONS_DIR}"))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    versions.sort();
+
+    for version in versions {
+        let already_applied: Option<(String,)> =
+            sqlx::query_as("SELECT version FROM schema_migrations WHERE version = ?")
+                .bind(&version)
+                .fetch_optional(pool)
+                .await
+                .context("failed to check schema_migrations")?;
+
+        if already_applied.is_some() {
+            continue;
+        }
+
+        apply_migration(pool, &version).await?;
+    }
+
+    Ok(())
+}
+
+async fn apply_migration(pool: &SqlitePool, version: &str) -> Result<()> {
+    let up_path = Path::new(MIGRATIONS_DIR).join(version).join("up.sql");
+    let sql = std::fs::read_to_string(&up_path)
+        .with_context(|| format!("failed to read migration {up_path:?}"))?;
+
+    let mut tx = pool.begin().await?;
+    for statement in sql.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+        sqlx::query(statement).execute(&mut *tx).await?;
+    }
+    sqlx::query("INSERT INTO schema_migrations (version, applied_at) VALUES (?, ?)")
+        .bind(version)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    info!("applied migration {}", version);
+    Ok(())
+}
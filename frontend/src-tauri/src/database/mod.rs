@@ -0,0 +1,4 @@
+pub mod migrations;
+pub mod models;
+
+pub use migrations::run_pending_migrations;
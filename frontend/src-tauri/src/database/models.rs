@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Model {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub model_type: String,
+    pub size: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// SHA-256 hex digest of the imported weight file, once one has been attached.
+    pub content_hash: Option<String>,
+    /// Size in bytes of the imported weight file.
+    pub byte_length: Option<i64>,
+    /// Username of the model's creator.
+    pub owner: String,
+    /// True if concurrent edits left more than one live value for this
+    /// model; see `update_model` and [`ModelValue`].
+    pub conflicted: bool,
+    /// Opaque dotted-version-vector token for the value(s) above. Pass this
+    /// back as `update_model`'s `causal_context` to edit without losing a
+    /// concurrent write.
+    pub causal_context: String,
+}
+
+/// One surviving sibling value for a model whose metadata was edited
+/// concurrently from two places. The UI can show every [`ModelValue`] for
+/// a conflicted model and let the user pick (or merge) one.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ModelValue {
+    pub name: String,
+    pub description: Option<String>,
+    pub model_type: String,
+    pub size: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The causal context and live value(s) for a model's metadata. Returned by
+/// `update_model` after a write, and by `get_model_values` for a plain read —
+/// either way, `causal_context` is what the caller must echo back on its
+/// next `update_model` call to avoid clobbering a concurrent edit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateModelResult {
+    pub causal_context: String,
+    pub values: Vec<ModelValue>,
+    pub conflicted: bool,
+}
+
+/// A page of [`Model`]s returned by `get_models`, along with the cursor to
+/// pass back as `start_after` to fetch the next page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPage {
+    pub models: Vec<Model>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ModelTypeCount {
+    pub model_type: String,
+    pub count: i64,
+}
@@ -0,0 +1,62 @@
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+
+mod auth;
+mod causal;
+mod commands;
+mod database;
+mod storage;
+
+use auth::{is_admin_user, CurrentSession, Session};
+use causal::load_or_create_node_id;
+use sqlx::sqlite::SqlitePoolOptions;
+use storage::ModelFileStore;
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let database_url =
+        std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite://hub.db".to_string());
+    let pool = SqlitePoolOptions::new()
+        .connect(&database_url)
+        .await
+        .expect("failed to connect to database");
+
+    database::run_pending_migrations(&pool)
+        .await
+        .expect("failed to run pending migrations");
+
+    let store_root = std::env::var("MODEL_FILE_STORE_DIR").unwrap_or_else(|_| "model_files".to_string());
+    let store = ModelFileStore::new(store_root);
+
+    // TODO: replace with the real login flow once one exists; for now the
+    // signed-in user is taken from the OS session, and admin status from a
+    // stop-gap ADMIN_USERS allowlist (see auth::is_admin_user).
+    let username = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
+    let is_admin = is_admin_user(&username);
+    let session = CurrentSession::new(Session { username, is_admin });
+
+    let node_id = load_or_create_node_id(&pool)
+        .await
+        .expect("failed to load or create node id");
+
+    tauri::Builder::default()
+        .manage(pool)
+        .manage(store)
+        .manage(session)
+        .manage(node_id)
+        .invoke_handler(tauri::generate_handler![
+            commands::models::get_models,
+            commands::models::get_models_by_user,
+            commands::models::get_model_counts,
+            commands::models::create_model,
+            commands::models::import_model_file,
+            commands::models::update_model,
+            commands::models::get_model_values,
+            commands::models::delete_model,
+            commands::admin::models_stats,
+            commands::admin::repair_models,
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
@@ -0,0 +1,75 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Content-addressed store for model weight files. Blobs are fanned out into
+/// `<hash[0..2]>/<hash[2..4]>/<hash>` so no single directory grows unbounded,
+/// and files with identical contents are stored exactly once.
+pub struct ModelFileStore {
+    root: PathBuf,
+}
+
+impl ModelFileStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn blob_path(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[0..2]).join(&hash[2..4]).join(hash)
+    }
+
+    /// Streams `source` into the store, hashing it as it's copied. If a blob
+    /// with the resulting hash already exists, the copy is discarded and the
+    /// existing blob is reused. Returns the hash and byte length.
+    pub async fn import(&self, source: &Path) -> Result<(String, u64)> {
+        let mut input = File::open(source)
+            .await
+            .with_context(|| format!("failed to open {source:?}"))?;
+
+        let staging_path = self.root.join(format!(".staging-{}", uuid::Uuid::new_v4()));
+        if let Some(parent) = staging_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut staging = File::create(&staging_path).await?;
+
+        let mut hasher = Sha256::new();
+        let mut buf = [0u8; 64 * 1024];
+        let mut byte_length: u64 = 0;
+        loop {
+            let n = input.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            staging.write_all(&buf[..n]).await?;
+            byte_length += n as u64;
+        }
+        staging.flush().await?;
+
+        let hash = hex::encode(hasher.finalize());
+        let final_path = self.blob_path(&hash);
+
+        if tokio::fs::metadata(&final_path).await.is_ok() {
+            tokio::fs::remove_file(&staging_path).await.ok();
+        } else {
+            if let Some(parent) = final_path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::rename(&staging_path, &final_path).await?;
+        }
+
+        Ok((hash, byte_length))
+    }
+
+    pub async fn remove_blob(&self, hash: &str) -> Result<()> {
+        let path = self.blob_path(hash);
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}